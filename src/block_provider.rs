@@ -0,0 +1,196 @@
+use {
+  super::*,
+  bitcoin::consensus::encode,
+};
+
+/// Abstraction over where `Index` gets its raw chain data from. The default
+/// implementation talks to a locally trusted `bitcoind` over RPC; `Esplora`
+/// lets the indexer run against a remote Esplora-compatible REST API instead,
+/// at the cost of trusting that server for block and transaction data.
+pub(crate) trait BlockProvider: Send + Sync {
+  fn block_count(&self) -> Result<u64>;
+  fn block_hash(&self, height: u64) -> Result<BlockHash>;
+  fn block(&self, hash: BlockHash) -> Result<Block>;
+  fn block_header(&self, hash: BlockHash) -> Result<bitcoin::block::Header>;
+  fn raw_transaction(&self, txid: Txid) -> Result<Transaction>;
+}
+
+/// The original, fully-trusted backend: a JSON-RPC connection to `bitcoind`.
+pub(crate) struct RpcBlockProvider {
+  client: Client,
+}
+
+impl RpcBlockProvider {
+  pub(crate) fn new(client: Client) -> Self {
+    Self { client }
+  }
+}
+
+impl BlockProvider for RpcBlockProvider {
+  fn block_count(&self) -> Result<u64> {
+    Ok(self.client.get_block_count()?)
+  }
+
+  fn block_hash(&self, height: u64) -> Result<BlockHash> {
+    Ok(self.client.get_block_hash(height)?)
+  }
+
+  fn block(&self, hash: BlockHash) -> Result<Block> {
+    Ok(self.client.get_block(&hash)?)
+  }
+
+  fn block_header(&self, hash: BlockHash) -> Result<bitcoin::block::Header> {
+    Ok(self.client.get_block_header(&hash)?)
+  }
+
+  fn raw_transaction(&self, txid: Txid) -> Result<Transaction> {
+    Ok(self.client.get_raw_transaction(&txid, None)?)
+  }
+}
+
+/// A read-only backend that fetches blocks and transactions from an
+/// Esplora-style REST API (the same API served by `blockstream.info` or a
+/// self-hosted `electrs`/`esplora` instance), so the indexer can run without
+/// a local, fully-validating node.
+pub(crate) struct EsploraBlockProvider {
+  agent: ureq::Agent,
+  url: String,
+}
+
+impl EsploraBlockProvider {
+  pub(crate) fn new(url: String) -> Self {
+    Self {
+      agent: ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(30))
+        .build(),
+      url,
+    }
+  }
+
+  fn get(&self, path: &str) -> Result<ureq::Response> {
+    self
+      .agent
+      .get(&format!("{}{path}", self.url))
+      .call()
+      .with_context(|| format!("request to esplora endpoint `{}{path}` failed", self.url))
+  }
+
+  fn get_hex(&self, path: &str) -> Result<Vec<u8>> {
+    Ok(hex::decode(self.get(path)?.into_string()?.trim())?)
+  }
+}
+
+impl BlockProvider for EsploraBlockProvider {
+  fn block_count(&self) -> Result<u64> {
+    Ok(self.get("/blocks/tip/height")?.into_string()?.trim().parse()?)
+  }
+
+  fn block_hash(&self, height: u64) -> Result<BlockHash> {
+    Ok(self.get(&format!("/block-height/{height}"))?.into_string()?.trim().parse()?)
+  }
+
+  fn block(&self, hash: BlockHash) -> Result<Block> {
+    Ok(encode::deserialize(&self.get_hex(&format!("/block/{hash}/raw"))?)?)
+  }
+
+  fn block_header(&self, hash: BlockHash) -> Result<bitcoin::block::Header> {
+    Ok(self.block(hash)?.header)
+  }
+
+  fn raw_transaction(&self, txid: Txid) -> Result<Transaction> {
+    Ok(encode::deserialize(&self.get_hex(&format!("/tx/{txid}/hex"))?)?)
+  }
+}
+
+/// Which [`BlockProvider`] to construct, selected by `--chain-source`.
+///
+/// `Options::chain_source` combines this with `--esplora-url`/`--rpc-url`
+/// into a [`ChainSource`], and `Index` picks a backend by calling
+/// [`ChainSource::provider`] once at startup.
+#[derive(Debug, PartialEq, Clone, clap::ValueEnum)]
+pub(crate) enum ChainSourceKind {
+  BitcoinCore,
+  Esplora,
+}
+
+pub(crate) enum ChainSource {
+  BitcoinCore(Client),
+  Esplora(String),
+}
+
+impl ChainSource {
+  pub(crate) fn provider(self) -> Box<dyn BlockProvider> {
+    match self {
+      Self::BitcoinCore(client) => Box::new(RpcBlockProvider::new(client)),
+      Self::Esplora(url) => Box::new(EsploraBlockProvider::new(url)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    std::{
+      io::{Read, Write},
+      net::TcpListener,
+    },
+  };
+
+  /// Binds to an ephemeral port, answers the first request it receives with
+  /// a 200 and `body`, then stops, returning the base URL to hit.
+  fn respond_once(body: &str) -> String {
+    let body = body.to_string();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+      let (mut stream, _) = listener.accept().unwrap();
+
+      let mut buffer = [0; 1024];
+      let _ = stream.read(&mut buffer);
+
+      let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+      );
+      stream.write_all(response.as_bytes()).unwrap();
+    });
+
+    format!("http://{addr}")
+  }
+
+  #[test]
+  fn block_count_parses_a_valid_response() {
+    let provider = EsploraBlockProvider::new(respond_once("800000"));
+    assert_eq!(provider.block_count().unwrap(), 800000);
+  }
+
+  #[test]
+  fn block_count_errors_instead_of_panicking_on_malformed_response() {
+    let provider = EsploraBlockProvider::new(respond_once("not-a-number"));
+    assert!(provider.block_count().is_err());
+  }
+
+  #[test]
+  fn block_hash_parses_a_valid_response() {
+    let hash = BlockHash::from_byte_array([1; 32]);
+    let provider = EsploraBlockProvider::new(respond_once(&hash.to_string()));
+    assert_eq!(provider.block_hash(0).unwrap(), hash);
+  }
+
+  #[test]
+  fn block_hash_errors_instead_of_panicking_on_malformed_response() {
+    let provider = EsploraBlockProvider::new(respond_once("not-a-hash"));
+    assert!(provider.block_hash(0).is_err());
+  }
+
+  #[test]
+  fn raw_transaction_errors_instead_of_panicking_on_malformed_hex() {
+    let provider = EsploraBlockProvider::new(respond_once("not-hex-either"));
+    assert!(provider
+      .raw_transaction(Txid::from_byte_array([0; 32]))
+      .is_err());
+  }
+}
@@ -0,0 +1,381 @@
+use super::*;
+
+/// BIP157/158 "neutrino" style scanning: syncs compact filter headers from an
+/// untrusted P2P peer, tests the wallet's own scripts against each block's
+/// basic filter, and only downloads the full block (to run the normal
+/// inscription/sat extraction path) when a filter matches. This lets `ord
+/// wallet` locate its own UTXOs, sats, and inscriptions without a local,
+/// fully-validating node. Wired up from the `wallet` subcommands as an
+/// alternative to scanning against `bitcoind` directly.
+const FILTER_TYPE_BASIC: u8 = 0;
+
+/// Golomb-Rice coding parameter, fixed by BIP158 for basic filters.
+const P: u8 = 19;
+
+/// False-positive rate divisor, fixed by BIP158 for basic filters: 1/M.
+const M: u64 = 784_931;
+
+/// A single BIP158 basic filter for one block, together with the hash of that
+/// block, whose first 16 bytes key the filter's SipHash.
+pub(crate) struct CompactFilter {
+  block_hash: BlockHash,
+  n: u64,
+  golomb_coded_set: Vec<u8>,
+}
+
+impl CompactFilter {
+  pub(crate) fn new(block_hash: BlockHash, n: u64, golomb_coded_set: Vec<u8>) -> Self {
+    Self {
+      block_hash,
+      n,
+      golomb_coded_set,
+    }
+  }
+
+  fn siphash_key(&self) -> [u8; 16] {
+    let mut key = [0; 16];
+    let bytes: &[u8] = self.block_hash.as_ref();
+    key.copy_from_slice(&bytes[..16]);
+    key
+  }
+
+  fn hash_script(&self, script: &Script, key: &[u8; 16]) -> u64 {
+    let k0 = u64::from_le_bytes(key[0..8].try_into().unwrap());
+    let k1 = u64::from_le_bytes(key[8..16].try_into().unwrap());
+    siphash24(k0, k1, script.as_bytes()) % (self.n * M)
+  }
+
+  /// Returns true if any of `scripts` may be referenced by this block's
+  /// transactions. False positives are possible (that's the point of a
+  /// probabilistic filter); false negatives are not.
+  pub(crate) fn matches(&self, scripts: &[ScriptBuf]) -> Result<bool> {
+    // `hash_script` reduces mod `self.n * M`; a filter with no elements would
+    // divide by zero (an unconditional panic, not a `Result::Err`) before
+    // ever reaching the `targets.is_empty()` check below, and filters are
+    // untrusted peer data, so this has to be checked first.
+    if self.n == 0 {
+      return Ok(false);
+    }
+
+    let key = self.siphash_key();
+
+    let mut targets = scripts
+      .iter()
+      .map(|script| self.hash_script(script, &key))
+      .collect::<Vec<u64>>();
+    targets.sort_unstable();
+
+    if targets.is_empty() {
+      return Ok(false);
+    }
+
+    let mut reader = BitReader::new(&self.golomb_coded_set);
+    let mut accumulator = 0u64;
+    let mut target_index = 0;
+
+    while let Some(delta) = reader.read_golomb_rice(P) {
+      accumulator += delta;
+
+      while target_index < targets.len() && targets[target_index] < accumulator {
+        target_index += 1;
+      }
+
+      if target_index < targets.len() && targets[target_index] == accumulator {
+        return Ok(true);
+      }
+
+      if target_index >= targets.len() {
+        break;
+      }
+    }
+
+    Ok(false)
+  }
+}
+
+/// Minimal SipHash-2-4 over a byte string, as specified by BIP158 (two
+/// 64-bit keys, two compression rounds, four finalization rounds).
+fn siphash24(k0: u64, k1: u64, data: &[u8]) -> u64 {
+  let mut v0 = k0 ^ 0x736f_6d65_7073_6575;
+  let mut v1 = k1 ^ 0x646f_7261_6e64_6f6d;
+  let mut v2 = k0 ^ 0x6c79_6765_6e65_7261;
+  let mut v3 = k1 ^ 0x7465_6462_7974_6573;
+
+  macro_rules! round {
+    () => {
+      v0 = v0.wrapping_add(v1);
+      v1 = v1.rotate_left(13);
+      v1 ^= v0;
+      v0 = v0.rotate_left(32);
+      v2 = v2.wrapping_add(v3);
+      v3 = v3.rotate_left(16);
+      v3 ^= v2;
+      v0 = v0.wrapping_add(v3);
+      v3 = v3.rotate_left(21);
+      v3 ^= v0;
+      v2 = v2.wrapping_add(v1);
+      v1 = v1.rotate_left(17);
+      v1 ^= v2;
+      v2 = v2.rotate_left(32);
+    };
+  }
+
+  let mut chunks = data.chunks_exact(8);
+  for chunk in &mut chunks {
+    let m = u64::from_le_bytes(chunk.try_into().unwrap());
+    v3 ^= m;
+    round!();
+    round!();
+    v0 ^= m;
+  }
+
+  let remainder = chunks.remainder();
+  let mut last_block = [0u8; 8];
+  last_block[..remainder.len()].copy_from_slice(remainder);
+  // SipHash's finalization only ever uses the input length modulo 256; this
+  // is an intentional truncation per the algorithm's definition, not a bug.
+  last_block[7] = u8::try_from(data.len() % 256).unwrap();
+  let m = u64::from_le_bytes(last_block);
+  v3 ^= m;
+  round!();
+  round!();
+  v0 ^= m;
+
+  v2 ^= 0xff;
+  round!();
+  round!();
+  round!();
+  round!();
+
+  v0 ^ v1 ^ v2 ^ v3
+}
+
+/// Reads Golomb-Rice coded deltas out of a bitstream MSB-first, matching
+/// BIP158's `GCS` encoding; the caller sums them to recover the sorted set.
+struct BitReader<'a> {
+  data: &'a [u8],
+  bit_position: usize,
+}
+
+impl<'a> BitReader<'a> {
+  fn new(data: &'a [u8]) -> Self {
+    Self {
+      data,
+      bit_position: 0,
+    }
+  }
+
+  fn read_bit(&mut self) -> Option<bool> {
+    let byte_index = self.bit_position / 8;
+    if byte_index >= self.data.len() {
+      return None;
+    }
+    let bit = (self.data[byte_index] >> (7 - self.bit_position % 8)) & 1 == 1;
+    self.bit_position += 1;
+    Some(bit)
+  }
+
+  fn read_golomb_rice(&mut self, p: u8) -> Option<u64> {
+    let mut quotient = 0u64;
+    loop {
+      match self.read_bit()? {
+        true => quotient += 1,
+        false => break,
+      }
+    }
+
+    let mut remainder = 0u64;
+    for _ in 0..p {
+      remainder = (remainder << 1) | self.read_bit()? as u64;
+    }
+
+    Some((quotient << p) | remainder)
+  }
+}
+
+/// Tracks the verified chain of compact filter headers, so rescans only need
+/// to fetch and check filters for blocks beyond the last persisted header
+/// instead of re-downloading everything from genesis.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct FilterHeaderChain {
+  headers: BTreeMap<u64, [u8; 32]>,
+}
+
+impl FilterHeaderChain {
+  pub(crate) fn new() -> Self {
+    Self {
+      headers: BTreeMap::new(),
+    }
+  }
+
+  /// Loads a previously [`FilterHeaderChain::save`]d chain from `path`, or
+  /// starts a fresh one if nothing has been persisted there yet.
+  pub(crate) fn load(path: &Path) -> Result<Self> {
+    if !path.is_file() {
+      return Ok(Self::new());
+    }
+
+    serde_json::from_slice(&fs::read(path)?)
+      .with_context(|| format!("failed to parse filter header chain at {}", path.display()))
+  }
+
+  /// Persists the chain to `path`, so the next `wallet scan` can resume from
+  /// `tip` instead of rescanning from genesis.
+  pub(crate) fn save(&self, path: &Path) -> Result {
+    fs::write(path, serde_json::to_vec(self)?)
+      .with_context(|| format!("failed to persist filter header chain to {}", path.display()))
+  }
+
+  pub(crate) fn tip(&self) -> Option<u64> {
+    self.headers.keys().next_back().copied()
+  }
+
+  /// A filter header commits to the previous filter header, so this chain can
+  /// only be extended, never spliced; callers must verify continuity against
+  /// the peer-supplied `cfheaders` message before calling this.
+  pub(crate) fn push(&mut self, height: u64, filter_header: [u8; 32]) {
+    self.headers.insert(height, filter_header);
+  }
+
+  pub(crate) fn get(&self, height: u64) -> Option<[u8; 32]> {
+    self.headers.get(&height).copied()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Writes Golomb-Rice coded values MSB-first, the inverse of `BitReader`,
+  /// so tests can build a real GCS instead of only exercising empty input.
+  struct BitWriter {
+    bits: Vec<bool>,
+  }
+
+  impl BitWriter {
+    fn new() -> Self {
+      Self { bits: Vec::new() }
+    }
+
+    fn write_golomb_rice(&mut self, value: u64, p: u8) {
+      let quotient = value >> p;
+      for _ in 0..quotient {
+        self.bits.push(true);
+      }
+      self.bits.push(false);
+
+      for i in (0..p).rev() {
+        self.bits.push((value >> i) & 1 == 1);
+      }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+      let mut bytes = vec![0u8; self.bits.len().div_ceil(8)];
+      for (i, bit) in self.bits.into_iter().enumerate() {
+        if bit {
+          bytes[i / 8] |= 1 << (7 - i % 8);
+        }
+      }
+      bytes
+    }
+  }
+
+  /// Encodes `scripts` into a real, sorted, delta-coded GCS against
+  /// `block_hash`, the same construction a peer would serve in a `cfilter`
+  /// message, so `matches` can be tested against genuine filter data instead
+  /// of only the empty-filter short-circuit.
+  fn encode_filter(block_hash: BlockHash, scripts: &[ScriptBuf]) -> CompactFilter {
+    let bytes: &[u8] = block_hash.as_ref();
+    let mut key = [0; 16];
+    key.copy_from_slice(&bytes[..16]);
+    let k0 = u64::from_le_bytes(key[0..8].try_into().unwrap());
+    let k1 = u64::from_le_bytes(key[8..16].try_into().unwrap());
+
+    let n = scripts.len() as u64;
+
+    let mut hashes = scripts
+      .iter()
+      .map(|script| siphash24(k0, k1, script.as_bytes()) % (n * M))
+      .collect::<Vec<u64>>();
+    hashes.sort_unstable();
+
+    let mut writer = BitWriter::new();
+    let mut previous = 0;
+    for hash in hashes {
+      writer.write_golomb_rice(hash - previous, P);
+      previous = hash;
+    }
+
+    CompactFilter::new(block_hash, n, writer.into_bytes())
+  }
+
+  #[test]
+  fn siphash24_is_deterministic_and_key_dependent() {
+    let data = b"hello ordinals";
+    assert_eq!(siphash24(0, 0, data), siphash24(0, 0, data));
+    assert_ne!(siphash24(0, 0, data), siphash24(1, 0, data));
+  }
+
+  #[test]
+  fn filter_header_chain_tracks_tip() {
+    let mut chain = FilterHeaderChain::new();
+    assert_eq!(chain.tip(), None);
+    chain.push(0, [0; 32]);
+    chain.push(1, [1; 32]);
+    assert_eq!(chain.tip(), Some(1));
+    assert_eq!(chain.get(0), Some([0; 32]));
+  }
+
+  #[test]
+  fn filter_header_chain_round_trips_through_save_and_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("filter-headers.json");
+
+    let mut chain = FilterHeaderChain::new();
+    chain.push(0, [0; 32]);
+    chain.push(1, [1; 32]);
+    chain.save(&path).unwrap();
+
+    let loaded = FilterHeaderChain::load(&path).unwrap();
+    assert_eq!(loaded.tip(), Some(1));
+    assert_eq!(loaded.get(0), Some([0; 32]));
+  }
+
+  #[test]
+  fn filter_header_chain_load_without_a_saved_file_starts_fresh() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("does-not-exist.json");
+
+    assert_eq!(FilterHeaderChain::load(&path).unwrap().tip(), None);
+  }
+
+  #[test]
+  fn empty_filter_matches_nothing() {
+    let filter = CompactFilter::new(BlockHash::all_zeros(), 0, Vec::new());
+    assert!(!filter.matches(&[ScriptBuf::new()]).unwrap());
+  }
+
+  #[test]
+  fn matches_a_script_present_in_a_real_encoded_gcs() {
+    let block_hash = BlockHash::from_byte_array([7; 32]);
+
+    let wallet_script = ScriptBuf::from_hex("76a914000000000000000000000000000000000000000088ac").unwrap();
+    let other_script = ScriptBuf::from_hex("76a914ffffffffffffffffffffffffffffffffffffffff88ac").unwrap();
+
+    let filter = encode_filter(block_hash, &[wallet_script.clone(), other_script]);
+
+    assert!(filter.matches(&[wallet_script]).unwrap());
+  }
+
+  #[test]
+  fn does_not_match_a_script_absent_from_a_real_encoded_gcs() {
+    let block_hash = BlockHash::from_byte_array([7; 32]);
+
+    let in_filter = ScriptBuf::from_hex("76a914000000000000000000000000000000000000000088ac").unwrap();
+    let not_in_filter = ScriptBuf::from_hex("76a914ffffffffffffffffffffffffffffffffffffffff88ac").unwrap();
+
+    let filter = encode_filter(block_hash, &[in_filter]);
+
+    assert!(!filter.matches(&[not_in_filter]).unwrap());
+  }
+}
@@ -0,0 +1,42 @@
+use super::*;
+
+/// Resolved, process-wide settings derived from [`Options`] by
+/// [`Config::from_options`]: the reorg depth limit and the notify endpoint.
+/// The notifier itself is bound from that endpoint by `Index::open`.
+#[derive(Clone)]
+pub(crate) struct Config {
+  pub(crate) max_reorg_depth: u64,
+  pub(crate) notify: Option<String>,
+}
+
+impl Config {
+  pub(crate) fn from_options(options: &Options) -> Self {
+    Self {
+      max_reorg_depth: options.max_reorg_depth,
+      notify: options.notify.clone(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn from_options_copies_reorg_depth_and_notify_endpoint() {
+    let options = Options {
+      rpc_url: None,
+      bitcoin_rpc_user: None,
+      bitcoin_rpc_pass: None,
+      chain_source: ChainSourceKind::BitcoinCore,
+      esplora_url: None,
+      notify: Some("127.0.0.1:9000".into()),
+      max_reorg_depth: 10,
+    };
+
+    let config = Config::from_options(&options);
+
+    assert_eq!(config.max_reorg_depth, 10);
+    assert_eq!(config.notify.as_deref(), Some("127.0.0.1:9000"));
+  }
+}
@@ -13,7 +13,9 @@
 use {
   self::{
     arguments::Arguments,
+    block_provider::{BlockProvider, ChainSource, ChainSourceKind},
     blocktime::Blocktime,
+    compact_filters::{CompactFilter, FilterHeaderChain},
     config::Config,
     decimal::Decimal,
     degree::Degree,
@@ -24,8 +26,10 @@ use {
     inscription::Inscription,
     inscription_id::InscriptionId,
     media::Media,
+    notifier::Notifier,
     options::Options,
     outgoing::Outgoing,
+    reorg::{BlockUndo, Reorg, ReorgError},
     representation::Representation,
     subcommand::{Subcommand, SubcommandResult},
     tally::Tally,
@@ -97,8 +101,10 @@ macro_rules! tprintln {
 }
 
 mod arguments;
+mod block_provider;
 mod blocktime;
 mod chain;
+mod compact_filters;
 mod config;
 mod decimal;
 mod degree;
@@ -110,11 +116,13 @@ mod index;
 mod inscription;
 pub mod inscription_id;
 mod media;
+mod notifier;
 mod object;
 mod options;
 mod outgoing;
 mod page_config;
 pub mod rarity;
+mod reorg;
 mod representation;
 pub mod sat;
 mod sat_point;
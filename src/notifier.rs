@@ -0,0 +1,201 @@
+use {
+  super::*,
+  std::{
+    io::{Read, Write},
+    net::TcpStream,
+  },
+};
+
+/// A tiny ZeroMQ-style pub/sub side channel for the running server, so
+/// downstream tools can react to chain activity without polling the HTTP
+/// API. `Notifier::bind` opens a `TcpListener` at the endpoint given by
+/// `--notify <endpoint>`; every connected subscriber is sent every published
+/// message, framed as `topic length | topic | payload length | payload |
+/// sequence number`, so a subscriber can tell a dropped message apart from a
+/// quiet chain by a gap in the sequence.
+pub(crate) struct Notifier {
+  sequence: atomic::AtomicU64,
+  subscribers: Mutex<Vec<TcpStream>>,
+  local_addr: std::net::SocketAddr,
+}
+
+/// A single published message. `sequence` increases monotonically across all
+/// topics, starting at zero for the lifetime of the server process.
+#[derive(Debug, Clone)]
+pub(crate) struct Notification {
+  pub(crate) topic: Topic,
+  pub(crate) payload: Vec<u8>,
+  pub(crate) sequence: u64,
+}
+
+impl Notification {
+  fn encode(&self) -> Vec<u8> {
+    let topic = self.topic.to_string();
+
+    let mut buffer = Vec::with_capacity(4 + topic.len() + 4 + self.payload.len() + 8);
+    buffer.extend_from_slice(&u32::try_from(topic.len()).unwrap().to_be_bytes());
+    buffer.extend_from_slice(topic.as_bytes());
+    buffer.extend_from_slice(&u32::try_from(self.payload.len()).unwrap().to_be_bytes());
+    buffer.extend_from_slice(&self.payload);
+    buffer.extend_from_slice(&self.sequence.to_be_bytes());
+    buffer
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub(crate) enum Topic {
+  #[display("rawblock")]
+  RawBlock,
+  #[display("inscription")]
+  Inscription,
+  #[display("rarity")]
+  Rarity,
+}
+
+impl Notifier {
+  /// Binds a `TcpListener` at `endpoint` and spawns a background thread that
+  /// accepts subscriber connections for the lifetime of the process, so
+  /// `--notify <endpoint>` is reachable by any process, not just the one
+  /// that created the `Notifier`.
+  pub(crate) fn bind(endpoint: &str) -> Result<Arc<Self>> {
+    let listener = TcpListener::bind(endpoint)
+      .with_context(|| format!("failed to bind notify endpoint {endpoint}"))?;
+
+    let local_addr = listener.local_addr()?;
+
+    let notifier = Arc::new(Self {
+      sequence: atomic::AtomicU64::new(0),
+      subscribers: Mutex::new(Vec::new()),
+      local_addr,
+    });
+
+    let accepting = notifier.clone();
+    thread::spawn(move || {
+      for stream in listener.incoming().flatten() {
+        accepting.subscribers.lock().unwrap().push(stream);
+      }
+    });
+
+    Ok(notifier)
+  }
+
+  pub(crate) fn local_addr(&self) -> std::net::SocketAddr {
+    self.local_addr
+  }
+
+  fn publish(&self, topic: Topic, payload: Vec<u8>) {
+    let sequence = self.sequence.fetch_add(1, atomic::Ordering::Relaxed);
+
+    let message = Notification {
+      topic,
+      payload,
+      sequence,
+    }
+    .encode();
+
+    self
+      .subscribers
+      .lock()
+      .unwrap()
+      .retain_mut(|subscriber| subscriber.write_all(&message).is_ok());
+  }
+
+  /// Called by the indexer loop once a block has been fully indexed,
+  /// alongside the existing graceful-shutdown checks.
+  pub(crate) fn notify_block(&self, block: &Block) {
+    let mut payload = Vec::new();
+    block
+      .consensus_encode(&mut payload)
+      .expect("in-memory encoding should not fail");
+    self.publish(Topic::RawBlock, payload);
+  }
+
+  pub(crate) fn notify_inscription(&self, inscription_id: InscriptionId, sat_point: SatPoint) {
+    self.publish(
+      Topic::Inscription,
+      format!("{inscription_id} {sat_point}").into_bytes(),
+    );
+  }
+
+  pub(crate) fn notify_rarity(&self, sat: Sat, rarity: Rarity) {
+    if rarity < Rarity::Uncommon {
+      return;
+    }
+
+    self.publish(Topic::Rarity, format!("{sat} {rarity}").into_bytes());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn recv_message(stream: &mut TcpStream) -> Notification {
+    let mut topic_len = [0; 4];
+    stream.read_exact(&mut topic_len).unwrap();
+    let mut topic = vec![0; u32::from_be_bytes(topic_len) as usize];
+    stream.read_exact(&mut topic).unwrap();
+
+    let mut payload_len = [0; 4];
+    stream.read_exact(&mut payload_len).unwrap();
+    let mut payload = vec![0; u32::from_be_bytes(payload_len) as usize];
+    stream.read_exact(&mut payload).unwrap();
+
+    let mut sequence = [0; 8];
+    stream.read_exact(&mut sequence).unwrap();
+
+    Notification {
+      topic: match String::from_utf8(topic).unwrap().as_str() {
+        "rawblock" => Topic::RawBlock,
+        "inscription" => Topic::Inscription,
+        "rarity" => Topic::Rarity,
+        other => panic!("unknown topic {other}"),
+      },
+      payload,
+      sequence: u64::from_be_bytes(sequence),
+    }
+  }
+
+  #[test]
+  fn message_framing_includes_topic_length_payload_length_and_sequence() {
+    let message = Notification {
+      topic: Topic::Rarity,
+      payload: b"hi".to_vec(),
+      sequence: 7,
+    }
+    .encode();
+
+    assert_eq!(&message[0..4], 6u32.to_be_bytes());
+    assert_eq!(&message[4..10], b"rarity");
+    assert_eq!(&message[10..14], 2u32.to_be_bytes());
+    assert_eq!(&message[14..16], b"hi");
+    assert_eq!(&message[16..24], 7u64.to_be_bytes());
+  }
+
+  #[test]
+  fn subscribers_connected_over_tcp_receive_increasing_sequence_numbers() {
+    let notifier = Notifier::bind("127.0.0.1:0").unwrap();
+    let mut subscriber = TcpStream::connect(notifier.local_addr()).unwrap();
+    thread::sleep(Duration::from_millis(50));
+
+    notifier.notify_rarity(Sat(0), Rarity::Mythic);
+    notifier.notify_rarity(Sat(1), Rarity::Legendary);
+
+    assert_eq!(recv_message(&mut subscriber).sequence, 0);
+    assert_eq!(recv_message(&mut subscriber).sequence, 1);
+  }
+
+  #[test]
+  fn common_sats_are_not_published() {
+    let notifier = Notifier::bind("127.0.0.1:0").unwrap();
+    let mut subscriber = TcpStream::connect(notifier.local_addr()).unwrap();
+    thread::sleep(Duration::from_millis(50));
+
+    notifier.notify_rarity(Sat(2099999997689999), Rarity::Common);
+    notifier.notify_rarity(Sat(0), Rarity::Mythic);
+
+    let message = recv_message(&mut subscriber);
+    assert_eq!(message.sequence, 0);
+    assert_eq!(message.topic, Topic::Rarity);
+  }
+}
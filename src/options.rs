@@ -0,0 +1,108 @@
+use super::*;
+
+/// CLI flags shared by every subcommand that needs to read the chain: how to
+/// reach it (`--chain-source`, `--rpc-url`/`--esplora-url`), how paranoid to
+/// be about reorgs (`--max-reorg-depth`), and whether to publish indexer
+/// events (`--notify`). `Arguments` flattens this into every subcommand, the
+/// same way the rest of the CLI's global flags work.
+#[derive(Debug, Parser, Clone)]
+pub(crate) struct Options {
+  #[arg(
+    long,
+    help = "Connect to Bitcoin Core RPC at <RPC_URL>. Only used when --chain-source=bitcoin-core."
+  )]
+  pub(crate) rpc_url: Option<String>,
+  #[arg(long)]
+  pub(crate) bitcoin_rpc_user: Option<String>,
+  #[arg(long)]
+  pub(crate) bitcoin_rpc_pass: Option<String>,
+  #[arg(
+    long,
+    value_enum,
+    default_value_t = ChainSourceKind::BitcoinCore,
+    help = "Fetch blocks and transactions from <CHAIN_SOURCE>."
+  )]
+  pub(crate) chain_source: ChainSourceKind,
+  #[arg(
+    long,
+    help = "Fetch blocks and transactions from the Esplora-compatible REST API at <ESPLORA_URL>. Required when --chain-source=esplora."
+  )]
+  pub(crate) esplora_url: Option<String>,
+  #[arg(
+    long,
+    help = "Publish rawblock/inscription/rarity notifications to subscribers connecting to <NOTIFY>."
+  )]
+  pub(crate) notify: Option<String>,
+  #[arg(
+    long,
+    default_value_t = 6,
+    help = "Halt indexing instead of unwinding a reorg deeper than <MAX_REORG_DEPTH> blocks."
+  )]
+  pub(crate) max_reorg_depth: u64,
+}
+
+impl Options {
+  /// Builds the [`ChainSource`] selected by `--chain-source`, validating
+  /// that the arguments it needs (`--esplora-url` for `esplora`) were given.
+  pub(crate) fn chain_source(&self) -> Result<ChainSource> {
+    match self.chain_source {
+      ChainSourceKind::BitcoinCore => {
+        let rpc_url = self
+          .rpc_url
+          .clone()
+          .unwrap_or_else(|| "127.0.0.1:8332".into());
+
+        let auth = match (&self.bitcoin_rpc_user, &self.bitcoin_rpc_pass) {
+          (Some(user), Some(pass)) => bitcoincore_rpc::Auth::UserPass(user.clone(), pass.clone()),
+          _ => bitcoincore_rpc::Auth::None,
+        };
+
+        Ok(ChainSource::BitcoinCore(Client::new(&rpc_url, auth)?))
+      }
+      ChainSourceKind::Esplora => Ok(ChainSource::Esplora(
+        self
+          .esplora_url
+          .clone()
+          .ok_or_else(|| anyhow!("--esplora-url is required when --chain-source=esplora"))?,
+      )),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn esplora_without_url_is_an_error() {
+    let options = Options {
+      rpc_url: None,
+      bitcoin_rpc_user: None,
+      bitcoin_rpc_pass: None,
+      chain_source: ChainSourceKind::Esplora,
+      esplora_url: None,
+      notify: None,
+      max_reorg_depth: 6,
+    };
+
+    assert!(options.chain_source().is_err());
+  }
+
+  #[test]
+  fn esplora_with_url_selects_esplora_source() {
+    let options = Options {
+      rpc_url: None,
+      bitcoin_rpc_user: None,
+      bitcoin_rpc_pass: None,
+      chain_source: ChainSourceKind::Esplora,
+      esplora_url: Some("https://blockstream.info/api".into()),
+      notify: None,
+      max_reorg_depth: 6,
+    };
+
+    assert!(matches!(
+      options.chain_source().unwrap(),
+      ChainSource::Esplora(url) if url == "https://blockstream.info/api"
+    ));
+  }
+}
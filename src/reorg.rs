@@ -0,0 +1,125 @@
+use super::*;
+
+/// Detects and unwinds chain reorganizations during indexing.
+///
+/// `Index` previously assumed the chain only ever grows linearly; a reorg
+/// would silently leave sat ranges, outpoints, and inscription assignments
+/// pointing at an orphaned branch. Before committing the block at height `H`,
+/// the updater now checks that its `prev_blockhash` matches the hash already
+/// stored for `H - 1` ([`Reorg::detect`]). On a mismatch, `Index::handle_reorg`
+/// walks backward block by block until it finds the common ancestor, then
+/// discards the orphaned blocks' undo log entries (and their `height_to_hash`
+/// rows), and indexing resumes right after the ancestor on the new branch.
+pub(crate) struct Reorg {}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum ReorgError {
+  /// `detect` only knows a reorg has happened, not how deep it goes; the
+  /// real depth is only known once `Index::handle_reorg` has walked back to
+  /// the common ancestor, so it isn't faked here.
+  Recoverable { height: u64 },
+  Unrecoverable,
+}
+
+impl fmt::Display for ReorgError {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      Self::Recoverable { height } => write!(f, "reorg detected at height {height}"),
+      Self::Unrecoverable => write!(f, "reorg depth exceeds index.max_reorg_depth"),
+    }
+  }
+}
+
+impl std::error::Error for ReorgError {}
+
+impl Reorg {
+  /// Compares `new_header`'s `prev_blockhash` against the hash the index has
+  /// stored for `height - 1`. `Ok(())` means the new block extends the tip we
+  /// already have; `Err` means a reorg has occurred and must be unwound by
+  /// walking backward from `height` (see `Index::handle_reorg`) to find the
+  /// common ancestor and measure the actual depth.
+  pub(crate) fn detect(
+    height: u64,
+    new_header: &bitcoin::block::Header,
+    stored_hash_at_previous_height: BlockHash,
+  ) -> Result<(), ReorgError> {
+    if height == 0 || new_header.prev_blockhash == stored_hash_at_previous_height {
+      return Ok(());
+    }
+
+    Err(ReorgError::Recoverable { height })
+  }
+
+  /// Caps how many blocks of undo history the indexer is willing to replay
+  /// before giving up and halting, so that a pathological or malicious reorg
+  /// cannot be used to silently corrupt index state by exceeding what was
+  /// ever recorded.
+  pub(crate) fn max_depth_exceeded(depth: u64, max_reorg_depth: u64) -> bool {
+    depth > max_reorg_depth
+  }
+}
+
+/// One block's worth of reversible index writes, keyed by the height they
+/// were applied at. `Index` pushes one of these per block onto its undo log
+/// as it indexes.
+pub(crate) struct BlockUndo {
+  pub(crate) height: u64,
+  pub(crate) block_hash: BlockHash,
+  pub(crate) sat_ranges_written: Vec<(OutPoint, u64, u64)>,
+  pub(crate) inscriptions_created: Vec<(InscriptionId, SatPoint)>,
+}
+
+impl BlockUndo {
+  pub(crate) fn new(height: u64, block_hash: BlockHash) -> Self {
+    Self {
+      height,
+      block_hash,
+      sat_ranges_written: Vec::new(),
+      inscriptions_created: Vec::new(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matching_prev_blockhash_is_not_a_reorg() {
+    let header = bitcoin::block::Header {
+      version: bitcoin::block::Version::ONE,
+      prev_blockhash: BlockHash::all_zeros(),
+      merkle_root: bitcoin::hash_types::TxMerkleNode::all_zeros(),
+      time: 0,
+      bits: bitcoin::CompactTarget::from_consensus(0),
+      nonce: 0,
+    };
+
+    assert_eq!(
+      Reorg::detect(1, &header, BlockHash::all_zeros()),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn genesis_block_is_never_a_reorg() {
+    let header = bitcoin::block::Header {
+      version: bitcoin::block::Version::ONE,
+      prev_blockhash: BlockHash::all_zeros(),
+      merkle_root: bitcoin::hash_types::TxMerkleNode::all_zeros(),
+      time: 0,
+      bits: bitcoin::CompactTarget::from_consensus(0),
+      nonce: 0,
+    };
+
+    // an unrelated stored hash would normally trip the mismatch check, but
+    // height 0 has no previous block to disagree with.
+    assert_eq!(Reorg::detect(0, &header, BlockHash::from_byte_array([1; 32])), Ok(()));
+  }
+
+  #[test]
+  fn max_depth_respects_configured_limit() {
+    assert!(!Reorg::max_depth_exceeded(6, 6));
+    assert!(Reorg::max_depth_exceeded(7, 6));
+  }
+}
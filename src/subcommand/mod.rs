@@ -0,0 +1,52 @@
+use super::*;
+
+pub(crate) mod repl;
+mod server;
+pub(crate) mod wallet;
+
+/// Every subcommand's result implements `Output`, so `main` can print it
+/// without each subcommand needing to know anything about JSON itself.
+/// Blanket-implemented for any `Serialize` type, matching how every
+/// subcommand's result type in this crate is just a plain serializable
+/// struct or enum.
+pub(crate) trait Output: Send {
+  fn print_json(&self);
+}
+
+impl<T: Serialize + Send> Output for T {
+  fn print_json(&self) {
+    println!(
+      "{}",
+      serde_json::to_string_pretty(self).expect("failed to serialize output")
+    );
+  }
+}
+
+pub(crate) type SubcommandResult = Result<Box<dyn Output>>;
+
+#[derive(Debug, Parser)]
+pub(crate) enum Subcommand {
+  /// Run the indexer against the configured chain source, publishing
+  /// notifications as new blocks are indexed.
+  Server,
+  /// Open an interactive session against the index, so a sequence of
+  /// lookups can be chained without paying the per-invocation redb
+  /// open/close cost every other subcommand pays.
+  Repl(repl::Repl),
+  /// Wallet-related subcommands, including scanning compact filters.
+  #[command(subcommand)]
+  Wallet(wallet::WalletSubcommand),
+}
+
+impl Subcommand {
+  pub(crate) fn run(self, options: Options) -> SubcommandResult {
+    match self {
+      Self::Server => server::run(options),
+      Self::Repl(repl) => {
+        let index = Index::open(&options)?;
+        repl.run(&index)
+      }
+      Self::Wallet(wallet) => wallet.run(options),
+    }
+  }
+}
@@ -0,0 +1,179 @@
+use {
+  super::*,
+  rustyline::{
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::Validator,
+    Context as RustylineContext, Editor, Helper,
+  },
+};
+
+/// Opens a persistent session against an already-open `Index`, so a sequence
+/// of lookups can be run without paying the per-invocation redb open/close
+/// cost that every other subcommand pays. Each line is one command; results
+/// print through the same `SubcommandResult`/`print_json` machinery the rest
+/// of the CLI uses, so output stays scriptable when stdin isn't a tty.
+#[derive(Debug, Parser)]
+pub(crate) struct Repl {}
+
+const COMMANDS: &[&str] = &[
+  "sat", "decode", "rarity", "degree", "height", "epoch", "inscriptions", "blocktime", "help",
+  "exit",
+];
+
+enum ReplCommand {
+  Sat(Sat),
+  Decode(String),
+  Rarity(Sat),
+  Degree(Sat),
+  Height(Sat),
+  Epoch(Sat),
+  Inscriptions(OutPoint),
+  Blocktime(u64),
+  Help,
+  Exit,
+}
+
+impl FromStr for ReplCommand {
+  type Err = Error;
+
+  fn from_str(line: &str) -> Result<Self> {
+    let mut words = line.split_whitespace();
+
+    let command = words.next().ok_or_else(|| anyhow!("empty command"))?;
+    let rest = words.collect::<Vec<&str>>().join(" ");
+
+    Ok(match command {
+      "sat" => Self::Sat(rest.parse()?),
+      "decode" => Self::Decode(rest),
+      "rarity" => Self::Rarity(rest.parse()?),
+      "degree" => Self::Degree(rest.parse()?),
+      "height" => Self::Height(rest.parse()?),
+      "epoch" => Self::Epoch(rest.parse()?),
+      "inscriptions" => Self::Inscriptions(rest.parse()?),
+      "blocktime" => Self::Blocktime(rest.parse()?),
+      "help" => Self::Help,
+      "exit" | "quit" => Self::Exit,
+      other => bail!("unknown command `{other}`, type `help` for a list"),
+    })
+  }
+}
+
+/// Tab-completes the fixed set of command names; readline history is handled
+/// by `rustyline`'s own `DefaultHistory` and needs no help from us.
+struct ReplHelper;
+
+impl Completer for ReplHelper {
+  type Candidate = Pair;
+
+  fn complete(
+    &self,
+    line: &str,
+    pos: usize,
+    _ctx: &RustylineContext<'_>,
+  ) -> rustyline::Result<(usize, Vec<Pair>)> {
+    let candidates = COMMANDS
+      .iter()
+      .filter(|command| command.starts_with(&line[..pos]))
+      .map(|command| Pair {
+        display: command.to_string(),
+        replacement: command.to_string(),
+      })
+      .collect();
+
+    Ok((0, candidates))
+  }
+}
+
+impl Hinter for ReplHelper {
+  type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+impl Repl {
+  pub(crate) fn run(self, index: &Index) -> SubcommandResult {
+    let mut editor = Editor::<ReplHelper, DefaultHistory>::new()?;
+    editor.set_helper(Some(ReplHelper));
+
+    loop {
+      match editor.readline("ord> ") {
+        Ok(line) if line.trim().is_empty() => continue,
+        Ok(line) => {
+          editor.add_history_entry(line.as_str()).ok();
+
+          match line.parse::<ReplCommand>().and_then(|command| self.execute(index, command)) {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(err) => eprintln!("error: {err}"),
+          }
+        }
+        Err(ReadlineError::Interrupted | ReadlineError::Eof) => break,
+        Err(err) => return Err(err.into()),
+      }
+    }
+
+    Ok(Box::new(Empty {}))
+  }
+
+  /// Returns `Ok(true)` when the REPL should exit.
+  fn execute(&self, index: &Index, command: ReplCommand) -> Result<bool> {
+    match command {
+      ReplCommand::Sat(sat) => print_json(index.find(sat)?)?,
+      ReplCommand::Decode(object) => print_json(object.parse::<Object>()?)?,
+      ReplCommand::Rarity(sat) => print_json(sat.rarity())?,
+      ReplCommand::Degree(sat) => print_json(sat.degree())?,
+      ReplCommand::Height(sat) => print_json(sat.height())?,
+      ReplCommand::Epoch(sat) => print_json(sat.epoch())?,
+      ReplCommand::Inscriptions(outpoint) => print_json(index.get_inscriptions_on_output(outpoint)?)?,
+      ReplCommand::Blocktime(height) => print_json(index.block_time(height)?)?,
+      ReplCommand::Help => {
+        println!("commands: {}", COMMANDS.join(", "));
+      }
+      ReplCommand::Exit => return Ok(true),
+    }
+
+    Ok(false)
+  }
+}
+
+fn print_json(value: impl Serialize) -> Result {
+  println!("{}", serde_json::to_string_pretty(&value)?);
+  Ok(())
+}
+
+#[derive(Serialize)]
+struct Empty {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_known_commands() {
+    assert!(matches!("help".parse::<ReplCommand>().unwrap(), ReplCommand::Help));
+    assert!(matches!("exit".parse::<ReplCommand>().unwrap(), ReplCommand::Exit));
+  }
+
+  #[test]
+  fn rejects_unknown_commands() {
+    assert!("frobnicate".parse::<ReplCommand>().is_err());
+  }
+
+  #[test]
+  fn completer_matches_prefix() {
+    let completions = COMMANDS
+      .iter()
+      .filter(|command| command.starts_with("he"))
+      .collect::<Vec<_>>();
+
+    assert_eq!(completions, vec![&"height", &"help"]);
+  }
+}
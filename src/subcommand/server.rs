@@ -0,0 +1,23 @@
+use super::*;
+
+/// Runs the indexer loop against whatever chain source `Options` resolves to
+/// (Bitcoin Core RPC or an Esplora-compatible REST API), publishing notifier
+/// events as new blocks land, until a graceful shutdown is requested.
+pub(crate) fn run(options: Options) -> SubcommandResult {
+  let index = Index::open(&options)?;
+
+  loop {
+    index.update()?;
+
+    if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+      break;
+    }
+
+    thread::sleep(Duration::from_secs(5));
+  }
+
+  Ok(Box::new(Empty {}))
+}
+
+#[derive(Serialize)]
+struct Empty {}
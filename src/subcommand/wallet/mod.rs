@@ -0,0 +1,19 @@
+use super::*;
+
+pub(crate) mod scan;
+
+#[derive(Debug, clap::Subcommand)]
+pub(crate) enum WalletSubcommand {
+  /// Scan an untrusted peer's BIP157/158 compact filters for this wallet's
+  /// own UTXOs, sats, and inscriptions instead of requiring a local,
+  /// fully-validating node.
+  Scan(scan::Scan),
+}
+
+impl WalletSubcommand {
+  pub(crate) fn run(self, options: Options) -> SubcommandResult {
+    match self {
+      Self::Scan(scan) => scan.run(options),
+    }
+  }
+}
@@ -0,0 +1,177 @@
+use {
+  super::*,
+  bitcoin::consensus::encode,
+  bitcoin::p2p::{
+    address::Address as PeerAddress,
+    message::{NetworkMessage, RawNetworkMessage},
+    message_filter::{CFHeaders, CFilter, GetCFHeaders, GetCFilters},
+    message_network::VersionMessage,
+    Magic, ServiceFlags,
+  },
+  std::{
+    net::TcpStream,
+    time::{SystemTime, UNIX_EPOCH},
+  },
+};
+
+/// Scans an untrusted peer's compact filters for this wallet's own scripts
+/// over the raw P2P protocol, downloading only the blocks that match rather
+/// than requiring a local full node to scan against.
+#[derive(Debug, Parser)]
+pub(crate) struct Scan {
+  #[arg(long, help = "Connect to <PEER> (host:port) for the P2P handshake.")]
+  peer: String,
+  #[arg(
+    long,
+    default_value = "wallet-filter-headers.json",
+    help = "Persist the verified filter header chain at <FILTER_HEADER_CHAIN_PATH>, so later scans resume instead of starting from genesis."
+  )]
+  filter_header_chain_path: PathBuf,
+}
+
+#[derive(Serialize)]
+struct ScanOutput {
+  matched_heights: Vec<u64>,
+}
+
+impl Scan {
+  pub(crate) fn run(self, options: Options) -> SubcommandResult {
+    let index = Index::open(&options)?;
+    let scripts = index.wallet_scripts()?;
+
+    let mut client = CompactFilterClient::connect(&self.peer)?;
+
+    let mut chain = FilterHeaderChain::load(&self.filter_header_chain_path)?;
+    let tip = index.chain_tip_height()?;
+    let start = chain.tip().map_or(0, |height| height + 1);
+
+    if start < tip {
+      client.sync_filter_headers(&mut chain, start)?;
+      chain.save(&self.filter_header_chain_path)?;
+    }
+
+    let mut matched_heights = Vec::new();
+
+    for height in start..tip {
+      let filter = client.fetch_filter(height)?;
+
+      if filter.matches(&scripts)? {
+        matched_heights.push(height);
+        index.index_block(height)?;
+      }
+    }
+
+    Ok(Box::new(ScanOutput { matched_heights }))
+  }
+}
+
+/// A minimal synchronous P2P client speaking just enough of the protocol to
+/// perform the version handshake and exchange `getcfheaders`/`cfheaders` and
+/// `getcfilters`/`cfilter` messages with an untrusted peer.
+struct CompactFilterClient {
+  stream: TcpStream,
+}
+
+impl CompactFilterClient {
+  fn connect(peer: &str) -> Result<Self> {
+    let stream =
+      TcpStream::connect(peer).with_context(|| format!("failed to connect to peer {peer}"))?;
+    let mut client = Self { stream };
+    client.handshake(peer)?;
+    Ok(client)
+  }
+
+  fn handshake(&mut self, peer: &str) -> Result {
+    let peer_addr = peer.parse().with_context(|| format!("invalid peer address {peer}"))?;
+    let local_addr = self.stream.local_addr()?;
+    let timestamp = unix_time()?;
+
+    let version = NetworkMessage::Version(VersionMessage::new(
+      ServiceFlags::NONE,
+      timestamp,
+      PeerAddress::new(&peer_addr, ServiceFlags::NONE),
+      PeerAddress::new(&local_addr, ServiceFlags::NONE),
+      u64::try_from(timestamp)?,
+      String::from("/ord:compact-filters/"),
+      0,
+    ));
+
+    self.send(version)?;
+    self.recv()?;
+    self.send(NetworkMessage::Verack)?;
+    self.recv()?;
+
+    Ok(())
+  }
+
+  fn send(&mut self, message: NetworkMessage) -> Result {
+    RawNetworkMessage::new(Magic::BITCOIN, message).consensus_encode(&mut self.stream)?;
+    Ok(())
+  }
+
+  fn recv(&mut self) -> Result<NetworkMessage> {
+    Ok(RawNetworkMessage::consensus_decode(&mut self.stream)?.payload().clone())
+  }
+
+  /// Requests filter headers from `start` onward, checks that they connect
+  /// to the header `chain` already holds for `start - 1`, and extends
+  /// `chain` with them; `Scan::run` persists the result, so a later call
+  /// only has to request what's missing.
+  fn sync_filter_headers(&mut self, chain: &mut FilterHeaderChain, start: u64) -> Result {
+    self.send(NetworkMessage::GetCFHeaders(GetCFHeaders {
+      filter_type: 0,
+      start_height: u32::try_from(start)?,
+      stop_hash: BlockHash::all_zeros(),
+    }))?;
+
+    if let NetworkMessage::CFHeaders(CFHeaders {
+      previous_filter_header,
+      filter_hashes,
+      ..
+    }) = self.recv()?
+    {
+      if let Some(stored) = start.checked_sub(1).and_then(|height| chain.get(height)) {
+        if stored != previous_filter_header.to_byte_array() {
+          bail!("peer's filter headers do not connect to the stored chain at height {start}");
+        }
+      }
+
+      let mut filter_header = previous_filter_header;
+      for (offset, filter_hash) in filter_hashes.into_iter().enumerate() {
+        filter_header = filter_hash.filter_header(&filter_header);
+        chain.push(start + offset as u64, filter_header.to_byte_array());
+      }
+    }
+
+    Ok(())
+  }
+
+  fn fetch_filter(&mut self, height: u64) -> Result<CompactFilter> {
+    self.send(NetworkMessage::GetCFilters(GetCFilters {
+      filter_type: 0,
+      start_height: u32::try_from(height)?,
+      stop_hash: BlockHash::all_zeros(),
+    }))?;
+
+    match self.recv()? {
+      NetworkMessage::CFilter(CFilter {
+        block_hash, filter, ..
+      }) => {
+        // BIP158 encodes `N` as a CompactSize prefix on the filter bytes
+        // themselves, rather than as a separate field on the `cfilter`
+        // message; decode it instead of treating the whole payload as the
+        // GCS bitstream with its element count equal to its byte length.
+        let (n, consumed) = encode::deserialize_partial::<encode::VarInt>(&filter)
+          .context("failed to decode compact filter's element count")?;
+        Ok(CompactFilter::new(block_hash, n.0, filter[consumed..].to_vec()))
+      }
+      other => bail!("expected cfilter message, got {other:?}"),
+    }
+  }
+}
+
+fn unix_time() -> Result<i64> {
+  Ok(i64::try_from(
+    SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+  )?)
+}